@@ -0,0 +1,52 @@
+// unreachable from the CLI until the parser/codegen stages that produce these nodes land
+#![allow(dead_code)]
+
+#[derive(Debug)]
+pub enum AProgramNode {
+    Program(AFunctionDefinitionNode),
+}
+
+#[derive(Debug)]
+pub enum AFunctionDefinitionNode {
+    Function(String, Vec<AInstructionNode>),
+}
+
+#[derive(Debug)]
+pub enum AInstructionNode {
+    Mov(AOperandNode, AOperandNode),
+    Unary(AUnaryOperatorNode, AOperandNode),
+    Binary(ABinaryOperatorNode, AOperandNode, AOperandNode),
+    Idiv(AOperandNode),
+    Cdq,
+    AllocateStack(i32),
+    Ret,
+}
+
+#[derive(Debug)]
+pub enum AUnaryOperatorNode {
+    Neg,
+    Not,
+}
+
+#[derive(Debug)]
+pub enum ABinaryOperatorNode {
+    Add,
+    Sub,
+    Mult,
+}
+
+#[derive(Debug)]
+pub enum AOperandNode {
+    Imm(i32),
+    Reg(ARegisterNode),
+    Pseudo(String),
+    Stack(i32),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum ARegisterNode {
+    AX,
+    DX,
+    R10,
+    R11,
+}
@@ -1,73 +1,226 @@
+// unreachable from the CLI until the parser/codegen stages that produce an AProgramNode land
+#![allow(dead_code)]
+
 use super::asm_tree::{
-    AFunctionDefinitionNode, AInstructionNode, AOperandNode, AProgramNode, ARegisterNode,
-    AUnaryOperatorNode,
+    ABinaryOperatorNode, AFunctionDefinitionNode, AInstructionNode, AOperandNode, AProgramNode,
+    ARegisterNode, AUnaryOperatorNode,
 };
 
+// GNU `as` (AT&T syntax) or NASM (Intel syntax); operand order is reversed between the two
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AsmDialect {
+    AttGas,
+    IntelNasm,
+}
+
 #[tracing::instrument(skip_all)]
-pub fn emit_program(a_program: AProgramNode, output: &mut String) {
+pub fn emit_program(a_program: AProgramNode, dialect: AsmDialect, output: &mut String) {
     let AProgramNode::Program(a_function) = a_program;
-    emit_function(a_function, output);
-    output.push_str("   .section .note.GNU-stack,\"\",@progbits\n");
+    emit_function(a_function, dialect, output);
+    match dialect {
+        AsmDialect::AttGas => output.push_str("   .section .note.GNU-stack,\"\",@progbits\n"),
+        AsmDialect::IntelNasm => {
+            output.push_str("section .note.GNU-stack noalloc noexec nowrite progbits\n")
+        }
+    }
 }
 
-fn emit_prologue(output: &mut String) {
-    output.push_str(&format!("    pushq %rbp\n"));
-    output.push_str(&format!("    movq %rsp, %rbp\n"));
+pub fn dump_asm(a_program: AProgramNode, dialect: AsmDialect) -> String {
+    let mut output = String::new();
+    emit_program(a_program, dialect, &mut output);
+    output
 }
 
-fn emit_epilogue(output: &mut String) {
-    output.push_str(&format!("   movq %rbp, %rsp\n"));
-    output.push_str(&format!("   popq %rbp\n"));
+fn emit_prologue(dialect: AsmDialect, output: &mut String) {
+    match dialect {
+        AsmDialect::AttGas => {
+            output.push_str("    pushq %rbp\n");
+            output.push_str("    movq %rsp, %rbp\n");
+        }
+        AsmDialect::IntelNasm => {
+            output.push_str("    push    rbp\n");
+            output.push_str("    mov     rbp, rsp\n");
+        }
+    }
+}
+
+fn emit_epilogue(dialect: AsmDialect, output: &mut String) {
+    match dialect {
+        AsmDialect::AttGas => {
+            output.push_str("   movq %rbp, %rsp\n");
+            output.push_str("   popq %rbp\n");
+        }
+        AsmDialect::IntelNasm => {
+            output.push_str("   mov     rsp, rbp\n");
+            output.push_str("   pop     rbp\n");
+        }
+    }
 }
 
-fn emit_function(a_function: AFunctionDefinitionNode, output: &mut String) {
+fn emit_function(a_function: AFunctionDefinitionNode, dialect: AsmDialect, output: &mut String) {
     let AFunctionDefinitionNode::Function(name, instructions) = a_function;
-    output.push_str(&format!("   .globl {name}\n"));
+    match dialect {
+        AsmDialect::AttGas => output.push_str(&format!("   .globl {name}\n")),
+        AsmDialect::IntelNasm => output.push_str(&format!("   global {name}\n")),
+    }
     output.push_str(&format!("{name}:\n"));
-    emit_prologue(output);
+    emit_prologue(dialect, output);
     for a_instruction in instructions {
-        emit_instructions(a_instruction, output);
+        emit_instructions(a_instruction, dialect, output);
     }
 }
 
-fn emit_instructions(a_instruction: AInstructionNode, output: &mut String) {
+fn emit_instructions(a_instruction: AInstructionNode, dialect: AsmDialect, output: &mut String) {
     match a_instruction {
         AInstructionNode::Mov(src, dst) => {
-            let src = direct_emit_operand(src);
-            let dst = direct_emit_operand(dst);
-            output.push_str(&format!("   movl    {src}, {dst}"));
+            let src = direct_emit_operand(src, dialect);
+            let dst = direct_emit_operand(dst, dialect);
+            match dialect {
+                AsmDialect::AttGas => output.push_str(&format!("   movl    {src}, {dst}")),
+                AsmDialect::IntelNasm => output.push_str(&format!("   mov     {dst}, {src}")),
+            }
         }
         AInstructionNode::Ret => {
-            emit_epilogue(output);
+            emit_epilogue(dialect, output);
             output.push_str("   ret");
         }
         AInstructionNode::Unary(operator, operand) => {
-            let operand = direct_emit_operand(operand);
-            let operator = direct_emit_operator(operator);
+            let operand = direct_emit_operand(operand, dialect);
+            let operator = direct_emit_operator(operator, dialect);
             output.push_str(&format!("   {operator}    {operand}"));
         }
-        AInstructionNode::AllocateStack(size) => {
-            output.push_str(&format!("  subq    ${size}, %rsp"));
+        AInstructionNode::AllocateStack(size) => match dialect {
+            AsmDialect::AttGas => output.push_str(&format!("  subq    ${size}, %rsp")),
+            AsmDialect::IntelNasm => output.push_str(&format!("  sub     rsp, {size}")),
+        },
+        AInstructionNode::Binary(operator, src, dst) => {
+            let src = direct_emit_operand(src, dialect);
+            let dst = direct_emit_operand(dst, dialect);
+            let operator = direct_emit_binary_operator(operator, dialect);
+            match dialect {
+                AsmDialect::AttGas => output.push_str(&format!("   {operator}    {src}, {dst}")),
+                AsmDialect::IntelNasm => output.push_str(&format!("   {operator}    {dst}, {src}")),
+            }
+        }
+        AInstructionNode::Idiv(operand) => {
+            let operand = direct_emit_operand(operand, dialect);
+            match dialect {
+                AsmDialect::AttGas => output.push_str(&format!("   idivl   {operand}")),
+                AsmDialect::IntelNasm => output.push_str(&format!("   idiv    {operand}")),
+            }
         }
+        AInstructionNode::Cdq => output.push_str("   cdq"),
     }
-    output.push_str("\n");
+    output.push('\n');
 }
 
-fn direct_emit_operand(a_operand: AOperandNode) -> String {
+fn direct_emit_operand(a_operand: AOperandNode, dialect: AsmDialect) -> String {
     match a_operand {
-        AOperandNode::Imm(c) => format!("${c}"),
-        AOperandNode::Reg(reg) => match reg {
-            ARegisterNode::AX => format!("%eax"),
-            ARegisterNode::R10 => format!("%r10d"),
+        AOperandNode::Imm(c) => match dialect {
+            AsmDialect::AttGas => format!("${c}"),
+            AsmDialect::IntelNasm => format!("{c}"),
+        },
+        AOperandNode::Reg(reg) => direct_emit_register(reg, dialect),
+        AOperandNode::Stack(addr) => match dialect {
+            AsmDialect::AttGas => format!("{addr}(%rbp)"),
+            AsmDialect::IntelNasm => format!("dword [rbp{addr:+}]"),
         },
-        AOperandNode::Stack(addr) => format!("{addr}(%rbp)"),
         _ => panic!("invalid operand found in emitter stage"),
     }
 }
 
-fn direct_emit_operator(a_operator: AUnaryOperatorNode) -> String {
-    match a_operator {
-        AUnaryOperatorNode::Neg => format!("negl"),
-        AUnaryOperatorNode::Not => format!("notl"),
+fn direct_emit_register(reg: ARegisterNode, dialect: AsmDialect) -> String {
+    match dialect {
+        AsmDialect::AttGas => match reg {
+            ARegisterNode::AX => "%eax".to_string(),
+            ARegisterNode::DX => "%edx".to_string(),
+            ARegisterNode::R10 => "%r10d".to_string(),
+            ARegisterNode::R11 => "%r11d".to_string(),
+        },
+        AsmDialect::IntelNasm => match reg {
+            ARegisterNode::AX => "eax".to_string(),
+            ARegisterNode::DX => "edx".to_string(),
+            ARegisterNode::R10 => "r10d".to_string(),
+            ARegisterNode::R11 => "r11d".to_string(),
+        },
+    }
+}
+
+fn direct_emit_operator(a_operator: AUnaryOperatorNode, dialect: AsmDialect) -> String {
+    match dialect {
+        AsmDialect::AttGas => match a_operator {
+            AUnaryOperatorNode::Neg => "negl".to_string(),
+            AUnaryOperatorNode::Not => "notl".to_string(),
+        },
+        AsmDialect::IntelNasm => match a_operator {
+            AUnaryOperatorNode::Neg => "neg".to_string(),
+            AUnaryOperatorNode::Not => "not".to_string(),
+        },
+    }
+}
+
+fn direct_emit_binary_operator(a_operator: ABinaryOperatorNode, dialect: AsmDialect) -> String {
+    match dialect {
+        AsmDialect::AttGas => match a_operator {
+            ABinaryOperatorNode::Add => "addl".to_string(),
+            ABinaryOperatorNode::Sub => "subl".to_string(),
+            ABinaryOperatorNode::Mult => "imull".to_string(),
+        },
+        AsmDialect::IntelNasm => match a_operator {
+            ABinaryOperatorNode::Add => "add".to_string(),
+            ABinaryOperatorNode::Sub => "sub".to_string(),
+            ABinaryOperatorNode::Mult => "imul".to_string(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn emit(instruction: AInstructionNode, dialect: AsmDialect) -> String {
+        let mut output = String::new();
+        emit_instructions(instruction, dialect, &mut output);
+        output
+    }
+
+    #[test]
+    fn mov_reverses_operand_order_for_intel() {
+        let mov = || AInstructionNode::Mov(AOperandNode::Imm(1), AOperandNode::Reg(ARegisterNode::AX));
+        assert_eq!(emit(mov(), AsmDialect::AttGas), "   movl    $1, %eax\n");
+        assert_eq!(emit(mov(), AsmDialect::IntelNasm), "   mov     eax, 1\n");
+    }
+
+    #[test]
+    fn binary_reverses_operand_order_for_intel() {
+        let add = || {
+            AInstructionNode::Binary(
+                ABinaryOperatorNode::Add,
+                AOperandNode::Reg(ARegisterNode::R10),
+                AOperandNode::Reg(ARegisterNode::AX),
+            )
+        };
+        assert_eq!(emit(add(), AsmDialect::AttGas), "   addl    %r10d, %eax\n");
+        assert_eq!(emit(add(), AsmDialect::IntelNasm), "   add    eax, r10d\n");
+    }
+
+    #[test]
+    fn idiv_picks_dialect_specific_mnemonic() {
+        let idiv = || AInstructionNode::Idiv(AOperandNode::Reg(ARegisterNode::R10));
+        assert_eq!(emit(idiv(), AsmDialect::AttGas), "   idivl   %r10d\n");
+        assert_eq!(emit(idiv(), AsmDialect::IntelNasm), "   idiv    r10d\n");
+    }
+
+    #[test]
+    fn cdq_is_dialect_independent() {
+        assert_eq!(emit(AInstructionNode::Cdq, AsmDialect::AttGas), "   cdq\n");
+        assert_eq!(emit(AInstructionNode::Cdq, AsmDialect::IntelNasm), "   cdq\n");
+    }
+
+    #[test]
+    fn allocate_stack_differs_in_operand_order_and_syntax() {
+        let alloc = || AInstructionNode::AllocateStack(16);
+        assert_eq!(emit(alloc(), AsmDialect::AttGas), "  subq    $16, %rsp\n");
+        assert_eq!(emit(alloc(), AsmDialect::IntelNasm), "  sub     rsp, 16\n");
     }
 }
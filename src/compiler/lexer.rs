@@ -1,9 +1,9 @@
+use colored::Colorize;
 use regex::Regex;
-use std::{
-    io::{Error, ErrorKind},
-    str::Chars,
-};
+use std::str::Chars;
 
+// pre-existing naming; renaming would ripple through every HandlingComment match arm below
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug)]
 pub enum CommentToken {
     LineComment,
@@ -11,7 +11,7 @@ pub enum CommentToken {
     PendingComment,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum SymbolToken {
     OpenParen,
     CloseParen,
@@ -33,6 +33,8 @@ pub enum KeywordToken {
     Return,
 }
 
+// fields are read by the parser stage, which isn't part of this chunk of the tree yet
+#[allow(dead_code)]
 #[derive(Debug)]
 pub enum Token {
     Identifier(String),
@@ -42,22 +44,92 @@ pub enum Token {
     Comment(CommentToken),
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    fn new(start: usize, end: usize) -> Self {
+        Span { start, end }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum LexErrorKind {
+    UnterminatedBlockComment,
+    UnexpectedEof,
+    InvalidToken(String),
+    StraySlash,
+}
+
+#[derive(Debug, Clone)]
+pub struct LexError {
+    pub offset: usize,
+    pub kind: LexErrorKind,
+}
+
+impl LexError {
+    fn message(&self, line: usize) -> String {
+        match &self.kind {
+            LexErrorKind::UnterminatedBlockComment => {
+                format!("unterminated block comment started at line {line}")
+            }
+            LexErrorKind::UnexpectedEof => format!("unexpected end of input at line {line}"),
+            LexErrorKind::InvalidToken(value) => {
+                format!("`{value}` did not match an identifier, keyword, or constant")
+            }
+            LexErrorKind::StraySlash => {
+                "stray `/` is not a valid token (did you mean `//` or `/*`?)".to_string()
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct OffsetChars<'a> {
+    chars: Chars<'a>,
+    offset: usize,
+}
+
+impl<'a> OffsetChars<'a> {
+    fn new(chars: Chars<'a>) -> Self {
+        OffsetChars { chars, offset: 0 }
+    }
+}
+
+impl<'a> Iterator for OffsetChars<'a> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        let next = self.chars.next();
+        if let Some(char) = next {
+            self.offset += char.len_utf8();
+        }
+        next
+    }
+}
+
 #[derive(Debug)]
 enum ReadState<'a> {
     Ready {
-        remaining_chars: Chars<'a>,
+        remaining_chars: OffsetChars<'a>,
     },
     Building {
-        remaining_chars: Chars<'a>,
+        remaining_chars: OffsetChars<'a>,
         current_value: String,
+        start: usize,
     },
     Done {
-        remaining_chars: Chars<'a>,
+        remaining_chars: OffsetChars<'a>,
         token: Token,
+        span: Span,
     },
     HandlingComment {
-        remaining_chars: Chars<'a>,
+        remaining_chars: OffsetChars<'a>,
         comment_value: CommentToken,
+        start: usize,
     },
     Exit,
 }
@@ -79,7 +151,35 @@ fn check_for_symbol(ch: char) -> Option<SymbolToken> {
     }
 }
 
-fn match_non_symbol_token(value: String) -> Result<Token, Error> {
+// extending the operator set is adding a row here, not touching the state machine below
+const MULTI_CHAR_OPERATORS: &[(&str, SymbolToken)] = &[("--", SymbolToken::Decrement)];
+
+fn match_operator(first: char, remaining_chars: &OffsetChars) -> Option<(SymbolToken, usize)> {
+    let mut candidate = first.to_string();
+    let peeked = remaining_chars.clone();
+    let mut best: Option<(SymbolToken, usize)> = None;
+    let mut consumed = 0;
+    for next_char in peeked {
+        candidate.push(next_char);
+        consumed += 1;
+        match MULTI_CHAR_OPERATORS
+            .iter()
+            .find(|(operator, _)| *operator == candidate)
+        {
+            Some((_, symbol)) => best = Some((*symbol, consumed)),
+            None if MULTI_CHAR_OPERATORS
+                .iter()
+                .any(|(operator, _)| operator.starts_with(&candidate)) =>
+            {
+                continue
+            }
+            None => break,
+        }
+    }
+    best
+}
+
+fn match_non_symbol_token(value: String) -> Result<Token, String> {
     use KeywordToken::*;
     use Token::*;
     match value.as_str() {
@@ -90,7 +190,7 @@ fn match_non_symbol_token(value: String) -> Result<Token, Error> {
     }
 }
 
-fn match_identifier_or_constant(value: String) -> Result<Token, Error> {
+fn match_identifier_or_constant(value: String) -> Result<Token, String> {
     // catching the weird inclusion of @ in [a-zA-Z_]
     let identifier: Regex = Regex::new(r"^[a-zA-Z_]\w*\b$").unwrap();
     if identifier.is_match(&value) {
@@ -100,132 +200,206 @@ fn match_identifier_or_constant(value: String) -> Result<Token, Error> {
     if constant.is_match(&value) {
         return Ok(Token::Constant(value));
     }
-    Err(Error::new(
-        ErrorKind::Other,
-        format!("{value} did not match an identifier or a constant"),
-    ))
+    Err(value)
 }
 
-fn consume<'a>(chars: Chars, mut vec: Vec<Token>) -> Vec<Token> {
+fn consume(chars: Chars, mut vec: Vec<(Token, Span)>) -> (Vec<(Token, Span)>, Vec<LexError>) {
     use ReadState::*;
     use Token::*;
+    let mut errors: Vec<LexError> = Vec::new();
     let mut state = Ready {
-        remaining_chars: chars,
+        remaining_chars: OffsetChars::new(chars),
     };
     loop {
         state = match state {
             Ready {
                 mut remaining_chars,
-            } => match remaining_chars.next() {
-                None => Exit,
-                Some(char) => match check_for_symbol(char) {
-                    Some(symbol) => match symbol {
-                        SymbolToken::CommentSymbol => HandlingComment {
-                            remaining_chars: remaining_chars,
-                            comment_value: CommentToken::PendingComment,
+            } => {
+                let start = remaining_chars.offset;
+                match remaining_chars.next() {
+                    None => Exit,
+                    Some(char) => match check_for_symbol(char) {
+                        Some(symbol) => match symbol {
+                            SymbolToken::CommentSymbol => HandlingComment {
+                                remaining_chars,
+                                comment_value: CommentToken::PendingComment,
+                                start,
+                            },
+                            _ => {
+                                let (symbol, end) = match match_operator(char, &remaining_chars) {
+                                    Some((matched, extra_chars)) => {
+                                        for _ in 0..extra_chars {
+                                            remaining_chars.next();
+                                        }
+                                        (matched, remaining_chars.offset)
+                                    }
+                                    None => (symbol, remaining_chars.offset),
+                                };
+                                Done {
+                                    remaining_chars,
+                                    token: Symbol(symbol),
+                                    span: Span::new(start, end),
+                                }
+                            }
                         },
-                        _ => Done {
+                        None => Building {
                             remaining_chars,
-                            token: Symbol(symbol),
+                            current_value: char.to_string(),
+                            start,
                         },
                     },
-                    None => Building {
-                        remaining_chars,
-                        current_value: char.to_string(),
-                    },
-                },
-            },
+                }
+            }
 
             HandlingComment {
                 mut remaining_chars,
                 comment_value: comment_token,
+                start,
             } => match comment_token {
-                CommentToken::PendingComment => match remaining_chars.next() {
-                    Some(char) => {
-                        if char == '/' {
-                            HandlingComment {
-                                remaining_chars,
-                                comment_value: CommentToken::LineComment,
-                            }
-                        } else if char == '*' {
-                            HandlingComment {
-                                remaining_chars,
-                                comment_value: CommentToken::BlockComment,
-                            }
-                        } else {
-                            panic!("Impossible comment value");
+                // peek, don't consume: if it's not a comment the char must stay unread
+                CommentToken::PendingComment => match remaining_chars.clone().next() {
+                    Some('/') => {
+                        remaining_chars.next();
+                        HandlingComment {
+                            remaining_chars,
+                            comment_value: CommentToken::LineComment,
+                            start,
+                        }
+                    }
+                    Some('*') => {
+                        remaining_chars.next();
+                        HandlingComment {
+                            remaining_chars,
+                            comment_value: CommentToken::BlockComment,
+                            start,
                         }
                     }
+                    Some(_) => {
+                        errors.push(LexError {
+                            offset: start,
+                            kind: LexErrorKind::StraySlash,
+                        });
+                        Ready { remaining_chars }
+                    }
                     None => {
-                        panic!("Unexpected EOF")
+                        errors.push(LexError {
+                            offset: start,
+                            kind: LexErrorKind::UnexpectedEof,
+                        });
+                        Exit
                     }
                 },
                 CommentToken::LineComment => match remaining_chars.next() {
                     Some(char) => {
                         if char == '\n' {
+                            let end = remaining_chars.offset;
                             Done {
                                 remaining_chars,
                                 token: Comment(CommentToken::LineComment),
+                                span: Span::new(start, end),
                             }
                         } else {
                             HandlingComment {
                                 remaining_chars,
                                 comment_value: CommentToken::LineComment,
+                                start,
                             }
                         }
                     }
+                    // a line comment may be legally unterminated at EOF
                     None => {
-                        panic!("Unexpected EOF")
+                        let end = remaining_chars.offset;
+                        Done {
+                            remaining_chars,
+                            token: Comment(CommentToken::LineComment),
+                            span: Span::new(start, end),
+                        }
                     }
                 },
-                CommentToken::BlockComment => {
-                    match remaining_chars.next().expect("Unexpected EOF") {
-                        '*' => match remaining_chars.next().expect("Unexpected EOF") {
-                            '/' => Done {
+                CommentToken::BlockComment => match remaining_chars.next() {
+                    Some('*') => match remaining_chars.next() {
+                        Some('/') => {
+                            let end = remaining_chars.offset;
+                            Done {
                                 remaining_chars,
                                 token: Comment(CommentToken::BlockComment),
-                            },
-                            _ => HandlingComment {
-                                remaining_chars,
-                                comment_value: CommentToken::BlockComment,
-                            },
-                        },
-                        _ => HandlingComment {
+                                span: Span::new(start, end),
+                            }
+                        }
+                        Some(_) => HandlingComment {
                             remaining_chars,
                             comment_value: CommentToken::BlockComment,
+                            start,
                         },
+                        None => {
+                            errors.push(LexError {
+                                offset: start,
+                                kind: LexErrorKind::UnterminatedBlockComment,
+                            });
+                            Exit
+                        }
+                    },
+                    Some(_) => HandlingComment {
+                        remaining_chars,
+                        comment_value: CommentToken::BlockComment,
+                        start,
+                    },
+                    None => {
+                        errors.push(LexError {
+                            offset: start,
+                            kind: LexErrorKind::UnterminatedBlockComment,
+                        });
+                        Exit
                     }
-                }
+                },
             },
 
             Building {
                 mut remaining_chars,
                 mut current_value,
+                start,
             } => match remaining_chars.clone().peekable().peek() {
-                None => Done {
-                    remaining_chars,
-                    token: match_non_symbol_token(current_value)
-                        .expect("Non-symbol token matching raised an error"),
-                },
-                Some(char) => {
-                    if let Some(_) = check_for_symbol(char.to_owned()) {
-                        Done {
-                            remaining_chars: remaining_chars,
-                            token: match_non_symbol_token(current_value)
-                                .expect("Non-symbol token matching raised an error"),
+                None => {
+                    let end = remaining_chars.offset;
+                    match match_non_symbol_token(current_value) {
+                        Ok(token) => Done {
+                            remaining_chars,
+                            token,
+                            span: Span::new(start, end),
+                        },
+                        Err(value) => {
+                            errors.push(LexError {
+                                offset: start,
+                                kind: LexErrorKind::InvalidToken(value),
+                            });
+                            Exit
                         }
-                    } else if char.to_owned() == ' ' {
-                        Done {
-                            remaining_chars: remaining_chars,
-                            token: match_non_symbol_token(current_value)
-                                .expect("Non-symbol token matching raised an error"),
+                    }
+                }
+                Some(char) => {
+                    if check_for_symbol(*char).is_some() || *char == ' ' {
+                        let end = remaining_chars.offset;
+                        match match_non_symbol_token(current_value) {
+                            Ok(token) => Done {
+                                remaining_chars,
+                                token,
+                                span: Span::new(start, end),
+                            },
+                            Err(value) => {
+                                errors.push(LexError {
+                                    offset: start,
+                                    kind: LexErrorKind::InvalidToken(value),
+                                });
+                                Ready { remaining_chars }
+                            }
                         }
                     } else {
-                        let new_char = remaining_chars.next().expect("Something weird happened");
+                        let new_char = remaining_chars.next().expect("peeked char must exist");
                         current_value.push(new_char);
                         Building {
-                            remaining_chars: remaining_chars,
-                            current_value: current_value,
+                            remaining_chars,
+                            current_value,
+                            start,
                         }
                     }
                 }
@@ -234,37 +408,102 @@ fn consume<'a>(chars: Chars, mut vec: Vec<Token>) -> Vec<Token> {
             Done {
                 remaining_chars,
                 token,
+                span,
             } => {
-                vec.push(token);
-                Ready {
-                    remaining_chars: remaining_chars.to_owned(),
-                }
+                vec.push((token, span));
+                Ready { remaining_chars }
             }
-            Exit => return vec,
+            Exit => return (vec, errors),
         };
     }
 }
 
-fn postprocess_tokens(mut tokens: Vec<Token>) -> Vec<Token> {
-    use SymbolToken::*;
-    let mut i = 0;
-    let mut length = tokens.len();
-    while i < length - 1 {
-        if let Token::Symbol(Minus) = tokens[i] {
-            if let Token::Symbol(Minus) = tokens[i + 1] {
-                tokens[i] = Token::Symbol(Decrement);
-                tokens.remove(i + 1);
-                length -= 1;
-            }
+pub fn dump_tokens(tokens: &[(Token, Span)]) -> String {
+    let mut output = String::new();
+    for (token, span) in tokens {
+        output.push_str(&format!("{}..{} {token:?}\n", span.start, span.end));
+    }
+    output
+}
+
+fn line_start_offsets(source: &str) -> Vec<usize> {
+    let mut starts = vec![0];
+    for (index, char) in source.char_indices() {
+        if char == '\n' {
+            starts.push(index + char.len_utf8());
         }
-        i += 1;
     }
-    tokens
+    starts
+}
+
+fn offset_to_line_col(line_starts: &[usize], offset: usize) -> (usize, usize) {
+    let line_index = match line_starts.binary_search(&offset) {
+        Ok(index) => index,
+        Err(index) => index - 1,
+    };
+    (line_index + 1, offset - line_starts[line_index] + 1)
+}
+
+fn render_lex_error(source: &str, line_starts: &[usize], error: &LexError) {
+    let (line, col) = offset_to_line_col(line_starts, error.offset);
+    let line_start = line_starts[line - 1];
+    let line_end = source[line_start..]
+        .find('\n')
+        .map(|relative| line_start + relative)
+        .unwrap_or(source.len());
+    let line_text = &source[line_start..line_end];
+
+    eprintln!("{} {}", "error:".red().bold(), error.message(line));
+    eprintln!("{line_text}");
+    eprintln!("{}{}", " ".repeat(col.saturating_sub(1)), "^".red().bold());
 }
 
-pub fn lex(code: String) -> Vec<Token> {
+pub fn lex(code: String) -> Result<Vec<(Token, Span)>, Vec<LexError>> {
     let chars = code.chars();
-    let vec = vec![];
-    let tokens = consume(chars, vec);
-    return postprocess_tokens(tokens);
+    let (tokens, errors) = consume(chars, vec![]);
+    if !errors.is_empty() {
+        let line_starts = line_start_offsets(&code);
+        for error in &errors {
+            render_lex_error(&code, &line_starts, error);
+        }
+        return Err(errors);
+    }
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unterminated_block_comment_reports_error() {
+        let (_, errors) = consume("/* never closed".chars(), vec![]);
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(errors[0].kind, LexErrorKind::UnterminatedBlockComment));
+    }
+
+    #[test]
+    fn stray_slash_does_not_swallow_the_next_char() {
+        let (tokens, errors) = consume("/x;".chars(), vec![]);
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(&errors[0].kind, LexErrorKind::StraySlash));
+        assert!(matches!(&tokens[0].0, Token::Identifier(name) if name == "x"));
+        assert!(matches!(tokens[1].0, Token::Symbol(SymbolToken::Semicolon)));
+    }
+
+    #[test]
+    fn adjacent_minuses_lex_as_decrement() {
+        let (tokens, errors) = consume("--x".chars(), vec![]);
+        assert!(errors.is_empty());
+        assert!(matches!(tokens[0].0, Token::Symbol(SymbolToken::Decrement)));
+    }
+
+    #[test]
+    fn whitespace_separated_minuses_stay_separate() {
+        let (tokens, errors) = consume("- -x".chars(), vec![]);
+        assert!(errors.is_empty());
+        assert!(matches!(tokens[0].0, Token::Symbol(SymbolToken::Minus)));
+        assert!(matches!(tokens[1].0, Token::Symbol(SymbolToken::Whitespace)));
+        assert!(matches!(tokens[2].0, Token::Symbol(SymbolToken::Minus)));
+    }
 }
@@ -0,0 +1,3 @@
+pub mod asm_tree;
+pub mod emitter;
+pub mod lexer;
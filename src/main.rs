@@ -0,0 +1,45 @@
+mod compiler;
+
+use compiler::lexer;
+use std::{env, fs, process};
+
+fn main() {
+    let mut dump_tokens = false;
+    let mut dump_asm = false;
+    let mut path = None;
+
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "-t" | "--dump-tokens" => dump_tokens = true,
+            "-a" | "--dump-asm" => dump_asm = true,
+            _ => path = Some(arg),
+        }
+    }
+
+    let Some(path) = path else {
+        eprintln!("usage: compiler [--dump-tokens] [--dump-asm] <file.c>");
+        process::exit(1);
+    };
+
+    let source = fs::read_to_string(&path).unwrap_or_else(|error| {
+        eprintln!("error: couldn't read {path}: {error}");
+        process::exit(1);
+    });
+
+    let tokens = match lexer::lex(source) {
+        Ok(tokens) => tokens,
+        Err(_) => process::exit(1),
+    };
+
+    if dump_tokens {
+        print!("{}", lexer::dump_tokens(&tokens));
+        return;
+    }
+
+    if dump_asm {
+        // The parser/codegen stages that lower `tokens` into an AProgramNode aren't part of
+        // this chunk of the tree yet, so there's nothing to hand `emitter::dump_asm`.
+        eprintln!("error: --dump-asm needs the parser/codegen stages, not present in this tree yet");
+        process::exit(1);
+    }
+}